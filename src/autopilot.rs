@@ -0,0 +1,391 @@
+//! Hamiltonian-cycle autopilot.
+//!
+//! The cycle visits every cell of the arena exactly once and loops back on
+//! itself, so always stepping to the cycle's next cell can never trap the
+//! snake. As an optimization we allow "shortcuts": jumping further ahead
+//! along the cycle toward the food, as long as the jump doesn't pass the
+//! tail's position in cycle order.
+
+use std::collections::{HashMap, HashSet};
+
+use bevy::{
+    ecs::{
+        change_detection::Res,
+        query::With,
+        system::{Commands, Query, ResMut, Resource},
+    },
+    input::{keyboard::KeyCode, ButtonInput},
+};
+
+use crate::{ArenaConfig, Direction, Food, Level, Position, SnakeHead, SnakeSegments};
+
+/// Whether the autopilot is currently steering the snake, toggled with `A`.
+/// Only meaningful while a `HamiltonianCycle` resource exists; see
+/// `toggle_autopilot`.
+#[derive(Resource, Default)]
+pub struct Autopilot {
+    pub enabled: bool,
+}
+
+/// A fixed Hamiltonian cycle over some region of the arena: an ordering of
+/// cells such that consecutive entries (including the last and first) are
+/// grid-adjacent, and every cell in the region appears exactly once.
+#[derive(Resource)]
+pub struct HamiltonianCycle {
+    cells: Vec<Position>,
+    index_of: HashMap<Position, usize>,
+}
+
+impl HamiltonianCycle {
+    /// Builds a cycle over a `width`x`height` region whose bottom-left
+    /// corner sits at `(origin_x, origin_y)` in arena coordinates.
+    ///
+    /// Reserves column 0 (of the region) as a vertical return lane and
+    /// snakes back and forth across the remaining columns, row by row; that
+    /// construction only closes into a genuine loop when `height` is even
+    /// (the last swept row lands back beside the lane). When `height` is
+    /// odd we instead reserve row 0 as a horizontal lane and snake up the
+    /// remaining rows column by column, which closes when `width` is even.
+    /// If both dimensions are odd, no construction here closes, and `None`
+    /// is returned rather than shipping a loop that silently isn't one.
+    fn build_offset(width: u32, height: u32, origin_x: i32, origin_y: i32) -> Option<Self> {
+        if width < 2 || height < 2 {
+            return None;
+        }
+
+        let local_cells = if height % 2 == 0 {
+            column_lane_cells(width, height)
+        } else if width % 2 == 0 {
+            row_lane_cells(width, height)
+        } else {
+            return None;
+        };
+
+        let cells = local_cells
+            .into_iter()
+            .map(|p| Position {
+                x: p.x + origin_x,
+                y: p.y + origin_y,
+            })
+            .collect();
+        Some(Self::from_cells(cells))
+    }
+
+    fn from_cells(cells: Vec<Position>) -> Self {
+        let index_of = cells.iter().enumerate().map(|(idx, &p)| (p, idx)).collect();
+        Self { cells, index_of }
+    }
+
+    fn index_of(&self, pos: Position) -> Option<usize> {
+        self.index_of.get(&pos).copied()
+    }
+
+    fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn at(&self, idx: usize) -> Position {
+        self.cells[idx % self.cells.len()]
+    }
+}
+
+/// Column 0 is a vertical return lane; the remaining columns are covered
+/// boustrophedon-style, row by row from the top back down to the bottom.
+/// Closes into a loop iff `height` is even.
+fn column_lane_cells(width: u32, height: u32) -> Vec<Position> {
+    let mut cells = Vec::with_capacity((width * height) as usize);
+
+    for y in 0..height as i32 {
+        cells.push(Position { x: 0, y });
+    }
+
+    for row in 0..height {
+        let y = height as i32 - 1 - row as i32;
+        if row % 2 == 0 {
+            for x in 1..width as i32 {
+                cells.push(Position { x, y });
+            }
+        } else {
+            for x in (1..width as i32).rev() {
+                cells.push(Position { x, y });
+            }
+        }
+    }
+
+    cells
+}
+
+/// Mirror image of `column_lane_cells`: row 0 is a horizontal return lane,
+/// and the remaining rows are covered column by column. Closes into a loop
+/// iff `width` is even.
+fn row_lane_cells(width: u32, height: u32) -> Vec<Position> {
+    let mut cells = Vec::with_capacity((width * height) as usize);
+
+    for x in 0..width as i32 {
+        cells.push(Position { x, y: 0 });
+    }
+
+    for col in 0..width {
+        let x = width as i32 - 1 - col as i32;
+        if col % 2 == 0 {
+            for y in 1..height as i32 {
+                cells.push(Position { x, y });
+            }
+        } else {
+            for y in (1..height as i32).rev() {
+                cells.push(Position { x, y });
+            }
+        }
+    }
+
+    cells
+}
+
+/// Finds the bounding rectangle of every non-wall cell in the arena, but
+/// only if that rectangle is entirely open floor — i.e. the level is a
+/// simple walled room, not one with interior obstacles the construction
+/// below can't route around. Returns `(origin_x, origin_y, width, height)`.
+fn open_rectangle(walls: &[Position], width: u32, height: u32) -> Option<(i32, i32, u32, u32)> {
+    let wall_set: HashSet<Position> = walls.iter().copied().collect();
+
+    let mut min_x = None;
+    let mut max_x = None;
+    let mut min_y = None;
+    let mut max_y = None;
+    for x in 0..width as i32 {
+        for y in 0..height as i32 {
+            if wall_set.contains(&Position { x, y }) {
+                continue;
+            }
+            min_x = Some(min_x.map_or(x, |m: i32| m.min(x)));
+            max_x = Some(max_x.map_or(x, |m: i32| m.max(x)));
+            min_y = Some(min_y.map_or(y, |m: i32| m.min(y)));
+            max_y = Some(max_y.map_or(y, |m: i32| m.max(y)));
+        }
+    }
+    let (min_x, max_x, min_y, max_y) = (min_x?, max_x?, min_y?, max_y?);
+
+    for x in min_x..=max_x {
+        for y in min_y..=max_y {
+            if wall_set.contains(&Position { x, y }) {
+                return None;
+            }
+        }
+    }
+
+    Some((min_x, min_y, (max_x - min_x + 1) as u32, (max_y - min_y + 1) as u32))
+}
+
+/// Builds the cycle over the level's open floor rather than the whole
+/// arena — a wall-bordered level has walls on column/row 0, which the
+/// unwalled construction would otherwise route straight through. If the
+/// open floor isn't a clean rectangle (e.g. interior obstacles), no cycle
+/// is built and the autopilot stays unavailable; see `toggle_autopilot`.
+pub fn build_hamiltonian_cycle(mut commands: Commands, config: Res<ArenaConfig>, level: Res<Level>) {
+    if let Some((origin_x, origin_y, width, height)) =
+        open_rectangle(&level.walls, config.width, config.height)
+    {
+        if let Some(cycle) = HamiltonianCycle::build_offset(width, height, origin_x, origin_y) {
+            commands.insert_resource(cycle);
+        }
+    }
+    commands.insert_resource(Autopilot::default());
+}
+
+/// Toggling only has an effect when a `HamiltonianCycle` was successfully
+/// built — otherwise there's nothing safe to steer with.
+pub fn toggle_autopilot(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    cycle: Option<Res<HamiltonianCycle>>,
+    mut autopilot: ResMut<Autopilot>,
+) {
+    if cycle.is_none() {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyA) {
+        autopilot.enabled = !autopilot.enabled;
+    }
+}
+
+fn forward_distance(total: usize, from: usize, to: usize) -> usize {
+    (to + total - from) % total
+}
+
+fn direction_between(from: Position, to: Position) -> Option<Direction> {
+    match (to.x - from.x, to.y - from.y) {
+        (1, 0) => Some(Direction::Right),
+        (-1, 0) => Some(Direction::Left),
+        (0, 1) => Some(Direction::Up),
+        (0, -1) => Some(Direction::Down),
+        _ => None,
+    }
+}
+
+/// Overrides `SnakeHead::direction` with the autopilot's choice. Scheduled
+/// after `snake_direction` so it takes priority over keyboard input
+/// whenever enabled.
+pub fn autopilot_steer(
+    autopilot: Res<Autopilot>,
+    cycle: Option<Res<HamiltonianCycle>>,
+    segments: Res<SnakeSegments>,
+    foods: Query<&Position, With<Food>>,
+    positions: Query<&Position>,
+    mut heads: Query<&mut SnakeHead>,
+) {
+    if !autopilot.enabled {
+        return;
+    }
+    let Some(cycle) = cycle else {
+        return;
+    };
+    let (Some(&head_entity), Some(&tail_entity)) = (segments.0.first(), segments.0.last()) else {
+        return;
+    };
+    let Ok(&head_pos) = positions.get(head_entity) else {
+        return;
+    };
+    let Ok(&tail_pos) = positions.get(tail_entity) else {
+        return;
+    };
+    // The arena's bounds check allows the head one cell further out than
+    // the cycle covers (a pre-existing off-by-one); if the head or tail
+    // ever lands there, just sit out this tick instead of indexing OOB.
+    let Some(head_idx) = cycle.index_of(head_pos) else {
+        return;
+    };
+    let Some(tail_idx) = cycle.index_of(tail_pos) else {
+        return;
+    };
+
+    let total = cycle.len();
+    let dist_to_tail = forward_distance(total, head_idx, tail_idx);
+
+    // Default: follow the cycle one step at a time.
+    let mut best_step = 1usize;
+
+    if let Some(food_pos) = foods.iter().next().copied() {
+        if let Some(food_idx) = cycle.index_of(food_pos) {
+            let dist_to_food = forward_distance(total, head_idx, food_idx);
+
+            for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                let neighbor = Position {
+                    x: head_pos.x + dx,
+                    y: head_pos.y + dy,
+                };
+                let Some(neighbor_idx) = cycle.index_of(neighbor) else {
+                    continue;
+                };
+                let step = forward_distance(total, head_idx, neighbor_idx);
+                // Never catch up to (or pass) the tail, and never overshoot
+                // the food — only take the shortcut if it's strictly
+                // closer than following the cycle plainly.
+                if step > 0 && step < dist_to_tail && step <= dist_to_food && step > best_step {
+                    best_step = step;
+                }
+            }
+        }
+    }
+
+    let next_pos = cycle.at(head_idx + best_step);
+    if let Some(direction) = direction_between(head_pos, next_pos) {
+        if let Ok(mut head) = heads.get_mut(head_entity) {
+            head.direction = direction;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// A cycle is only safe to follow if it covers every cell of the region
+    /// exactly once and every consecutive pair (including last -> first) is
+    /// a single grid step apart.
+    fn assert_valid_cycle(width: u32, height: u32, origin_x: i32, origin_y: i32) {
+        let cycle = HamiltonianCycle::build_offset(width, height, origin_x, origin_y)
+            .unwrap_or_else(|| panic!("expected a cycle for {width}x{height}"));
+
+        assert_eq!(cycle.cells.len(), (width * height) as usize);
+
+        let unique: HashSet<Position> = cycle.cells.iter().copied().collect();
+        assert_eq!(
+            unique.len(),
+            cycle.cells.len(),
+            "cycle must visit every cell exactly once"
+        );
+
+        for x in 0..width as i32 {
+            for y in 0..height as i32 {
+                let pos = Position {
+                    x: x + origin_x,
+                    y: y + origin_y,
+                };
+                assert!(unique.contains(&pos), "cycle is missing cell {pos:?}");
+            }
+        }
+
+        for i in 0..cycle.cells.len() {
+            let a = cycle.cells[i];
+            let b = cycle.cells[(i + 1) % cycle.cells.len()];
+            let step = (b.x - a.x).abs() + (b.y - a.y).abs();
+            assert_eq!(step, 1, "cells {a:?} -> {b:?} aren't grid-adjacent");
+        }
+    }
+
+    #[test]
+    fn even_height_cycle_is_valid() {
+        assert_valid_cycle(20, 20, 0, 0);
+        assert_valid_cycle(5, 4, 0, 0);
+    }
+
+    #[test]
+    fn even_width_odd_height_cycle_is_valid() {
+        assert_valid_cycle(4, 5, 0, 0);
+        assert_valid_cycle(6, 7, 0, 0);
+    }
+
+    #[test]
+    fn offset_cycle_is_valid() {
+        assert_valid_cycle(4, 6, 3, 2);
+    }
+
+    #[test]
+    fn both_dimensions_odd_has_no_closing_cycle() {
+        assert!(HamiltonianCycle::build_offset(5, 5, 0, 0).is_none());
+        assert!(HamiltonianCycle::build_offset(7, 3, 0, 0).is_none());
+    }
+
+    #[test]
+    fn degenerate_dimensions_have_no_cycle() {
+        assert!(HamiltonianCycle::build_offset(1, 4, 0, 0).is_none());
+        assert!(HamiltonianCycle::build_offset(4, 1, 0, 0).is_none());
+    }
+
+    #[test]
+    fn open_rectangle_finds_the_interior_of_a_bordered_room() {
+        // A 6x5 room bordered by walls on every edge.
+        let mut walls = Vec::new();
+        for x in 0..6 {
+            walls.push(Position { x, y: 0 });
+            walls.push(Position { x, y: 4 });
+        }
+        for y in 0..5 {
+            walls.push(Position { x: 0, y });
+            walls.push(Position { x: 5, y });
+        }
+
+        assert_eq!(open_rectangle(&walls, 6, 5), Some((1, 1, 4, 3)));
+    }
+
+    #[test]
+    fn open_rectangle_rejects_interior_obstacles() {
+        let walls = vec![Position { x: 2, y: 2 }];
+        assert_eq!(open_rectangle(&walls, 6, 6), None);
+    }
+
+    #[test]
+    fn open_rectangle_covers_the_whole_arena_with_no_walls() {
+        assert_eq!(open_rectangle(&[], 6, 6), Some((0, 0, 6, 6)));
+    }
+}
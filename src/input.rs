@@ -0,0 +1,116 @@
+//! Keyboard and gamepad input. `KeyBindings` lets players remap the four
+//! directional keys; `snake_direction` merges keyboard and gamepad input
+//! each frame, keyboard taking priority, while still enforcing the
+//! existing "can't reverse into yourself" rule.
+
+use bevy::{
+    ecs::system::{Query, Res, Resource},
+    input::{
+        gamepad::{GamepadAxis, GamepadAxisType, GamepadButton, GamepadButtonType, Gamepads},
+        keyboard::KeyCode,
+        Axis, ButtonInput,
+    },
+};
+
+use crate::{Direction, SnakeHead};
+
+/// Remappable key bindings for the four movement directions. Defaults to
+/// the original arrow keys.
+#[derive(Resource)]
+pub struct KeyBindings {
+    pub up: KeyCode,
+    pub down: KeyCode,
+    pub left: KeyCode,
+    pub right: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            up: KeyCode::ArrowUp,
+            down: KeyCode::ArrowDown,
+            left: KeyCode::ArrowLeft,
+            right: KeyCode::ArrowRight,
+        }
+    }
+}
+
+/// Stick movement below this magnitude is treated as centered, to avoid
+/// drift on worn analog sticks.
+const STICK_DEADZONE: f32 = 0.5;
+
+fn gamepad_direction(
+    gamepads: &Gamepads,
+    gamepad_buttons: &ButtonInput<GamepadButton>,
+    gamepad_axes: &Axis<GamepadAxis>,
+) -> Option<Direction> {
+    for gamepad in gamepads.iter() {
+        let dpad = [
+            (GamepadButtonType::DPadLeft, Direction::Left),
+            (GamepadButtonType::DPadRight, Direction::Right),
+            (GamepadButtonType::DPadUp, Direction::Up),
+            (GamepadButtonType::DPadDown, Direction::Down),
+        ];
+        for (button_type, direction) in dpad {
+            if gamepad_buttons.pressed(GamepadButton::new(gamepad, button_type)) {
+                return Some(direction);
+            }
+        }
+
+        let stick_x = gamepad_axes
+            .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
+            .unwrap_or(0.0);
+        let stick_y = gamepad_axes
+            .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))
+            .unwrap_or(0.0);
+
+        if stick_x.abs() > STICK_DEADZONE || stick_y.abs() > STICK_DEADZONE {
+            return Some(if stick_x.abs() > stick_y.abs() {
+                if stick_x > 0.0 {
+                    Direction::Right
+                } else {
+                    Direction::Left
+                }
+            } else if stick_y > 0.0 {
+                Direction::Up
+            } else {
+                Direction::Down
+            });
+        }
+    }
+    None
+}
+
+pub fn snake_direction(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    bindings: Res<KeyBindings>,
+    mut head_positions: Query<&mut SnakeHead>,
+) {
+    let keyboard_dir = if keyboard_input.pressed(bindings.left) {
+        Some(Direction::Left)
+    } else if keyboard_input.pressed(bindings.right) {
+        Some(Direction::Right)
+    } else if keyboard_input.pressed(bindings.up) {
+        Some(Direction::Up)
+    } else if keyboard_input.pressed(bindings.down) {
+        Some(Direction::Down)
+    } else {
+        None
+    };
+
+    let next_dir =
+        keyboard_dir.or_else(|| gamepad_direction(&gamepads, &gamepad_buttons, &gamepad_axes));
+
+    let Some(next_dir) = next_dir else {
+        return;
+    };
+
+    for mut snake_head in head_positions.iter_mut() {
+        if next_dir != snake_head.direction.opposite() {
+            snake_head.direction = next_dir;
+        }
+    }
+}
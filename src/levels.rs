@@ -0,0 +1,144 @@
+//! Level layouts: walls, the snake's start position, and extra food-spawn
+//! exclusions, described by a small grid format so stages can be authored
+//! as plain text files instead of hard-coded in source.
+//!
+//! Each row of the file is one row of the arena, top to bottom; each
+//! character is one cell:
+//!   `#` - wall
+//!   `S` - snake head start
+//!   `x` - food may never spawn here, even though it isn't a wall
+//!   `.` - open floor
+//!
+//! If `levels/level1.txt` can't be read, a small built-in layout is used
+//! instead so the game still runs out of the box.
+
+use bevy::{
+    color::Color,
+    ecs::{
+        component::Component,
+        system::{Commands, Res, Resource},
+    },
+    prelude::default,
+    sprite::{Sprite, SpriteBundle},
+};
+use std::fs;
+
+use crate::{Position, Size};
+
+const LEVEL_PATH: &str = "levels/level1.txt";
+
+const DEFAULT_LEVEL: &str = "\
+####################
+#..................#
+#..................#
+#..................#
+#........S.........#
+#..................#
+#..................#
+#..................#
+#..................#
+#..................#
+#..................#
+#..................#
+#..................#
+#..................#
+#..................#
+#..................#
+#..................#
+#..................#
+#..................#
+####################";
+
+#[derive(Component)]
+pub struct Wall;
+
+#[derive(Resource, Default)]
+pub struct Level {
+    pub walls: Vec<Position>,
+    pub snake_start: Option<Position>,
+    pub spawn_exclusions: Vec<Position>,
+}
+
+fn parse(text: &str) -> Level {
+    let mut level = Level::default();
+    let rows: Vec<&str> = text.lines().filter(|line| !line.is_empty()).collect();
+    let height = rows.len();
+
+    for (row_idx, row) in rows.iter().enumerate() {
+        // Row 0 of the file is the top of the map, but y grows upward in
+        // game space, so flip it here.
+        let y = (height - 1 - row_idx) as i32;
+        for (x, cell) in row.chars().enumerate() {
+            let pos = Position { x: x as i32, y };
+            match cell {
+                '#' => level.walls.push(pos),
+                'S' => level.snake_start = Some(pos),
+                'x' => level.spawn_exclusions.push(pos),
+                _ => {}
+            }
+        }
+    }
+
+    level
+}
+
+pub fn load_level(mut commands: Commands) {
+    let text = fs::read_to_string(LEVEL_PATH).unwrap_or_else(|_| DEFAULT_LEVEL.to_string());
+    commands.insert_resource(parse(&text));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walls_are_collected_with_y_flipped_so_the_top_row_is_highest() {
+        // 3 rows tall; the file's first line is the top of the map, which
+        // should land at the highest y.
+        let level = parse("#.#\n...\n#.#");
+        assert!(level.walls.contains(&Position { x: 0, y: 2 }));
+        assert!(level.walls.contains(&Position { x: 2, y: 2 }));
+        assert!(level.walls.contains(&Position { x: 0, y: 0 }));
+        assert!(level.walls.contains(&Position { x: 2, y: 0 }));
+        assert_eq!(level.walls.len(), 4);
+    }
+
+    #[test]
+    fn snake_start_is_read_from_s_and_flipped_the_same_way() {
+        let level = parse("...\n.S.\n...");
+        assert_eq!(level.snake_start, Some(Position { x: 1, y: 1 }));
+    }
+
+    #[test]
+    fn spawn_exclusions_are_read_from_x_and_dont_count_as_walls() {
+        let level = parse("...\n.x.\n...");
+        assert_eq!(level.spawn_exclusions, vec![Position { x: 1, y: 1 }]);
+        assert!(level.walls.is_empty());
+    }
+
+    #[test]
+    fn dots_and_blank_lines_produce_no_markers() {
+        let level = parse("...\n...\n\n...");
+        assert!(level.walls.is_empty());
+        assert!(level.spawn_exclusions.is_empty());
+        assert_eq!(level.snake_start, None);
+    }
+}
+
+const WALL_COLOR: Color = Color::srgb(0.5, 0.25, 0.2);
+
+pub fn spawn_walls(mut commands: Commands, level: Res<Level>) {
+    for &position in &level.walls {
+        commands
+            .spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: WALL_COLOR,
+                    ..default()
+                },
+                ..default()
+            })
+            .insert(Wall)
+            .insert(Size::square(0.9))
+            .insert(position);
+    }
+}
@@ -1,5 +1,14 @@
-use std::time::Duration;
+mod autopilot;
+mod input;
+mod levels;
 
+use std::{fs, path::PathBuf, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use autopilot::{autopilot_steer, build_hamiltonian_cycle, toggle_autopilot};
+use input::{snake_direction, KeyBindings};
+use levels::{load_level, spawn_walls, Level, Wall};
 use bevy::{
     app::{PostUpdate, Startup, Update},
     color::Color,
@@ -10,15 +19,20 @@ use bevy::{
         entity::Entity,
         event::{Event, EventWriter},
         query::{With, Without},
-        schedule::IntoSystemConfigs,
-        system::{Commands, Resource},
+        schedule::{
+            common_conditions::in_state, IntoSystemConfigs, NextState, OnEnter, OnExit, State,
+            States,
+        },
+        system::{Commands, Local, Resource},
     },
+    hierarchy::DespawnRecursiveExt,
     input::{keyboard::KeyCode, ButtonInput},
     math::Vec3,
     prelude::{default, App, EventReader, PluginGroup, Query, ResMut},
     render::camera::ClearColor,
     sprite::SpriteBundle,
-    time::common_conditions::on_timer,
+    text::{Text, Text2dBundle, TextStyle},
+    time::{common_conditions::on_timer, Time, Timer, TimerMode},
     transform::components::Transform,
     window::{PrimaryWindow, Window, WindowPlugin, WindowResolution},
     DefaultPlugins,
@@ -29,19 +43,52 @@ fn main() {
     App::new()
         .insert_resource(ClearColor(Color::srgb(0.04, 0.04, 0.04)))
         .insert_resource(SnakeSegments::default())
+        .insert_resource(Score::default())
+        .insert_resource(ArenaConfig::default())
+        .insert_resource(KeyBindings::default())
+        .init_state::<GameState>()
         .add_event::<GrowthEvent>()
         .add_event::<GameOverEvent>()
-        .add_systems(Startup, (setup_camera, spawn_snake))
+        .add_systems(
+            Startup,
+            (
+                setup_camera,
+                load_level,
+                spawn_walls,
+                spawn_snake,
+                load_high_score,
+                spawn_score_hud,
+                build_hamiltonian_cycle,
+            )
+                .chain(),
+        )
+        .add_systems(Update, (update_score_text, toggle_autopilot))
+        .add_systems(OnEnter(GameState::Menu), menu_setup)
+        .add_systems(OnExit(GameState::Menu), despawn_screen::<MenuScreen>)
+        .add_systems(OnEnter(GameState::GameOver), game_over_setup)
+        .add_systems(OnExit(GameState::GameOver), despawn_screen::<GameOverScreen>)
+        .add_systems(OnEnter(GameState::Paused), pause_setup)
+        .add_systems(OnExit(GameState::Paused), despawn_screen::<PauseScreen>)
+        .add_systems(
+            Update,
+            (
+                pause_toggle,
+                menu_input.run_if(in_state(GameState::Menu)),
+                game_over_input.run_if(in_state(GameState::GameOver)),
+            ),
+        )
         .add_systems(
             Update,
             (
                 snake_direction.before(snake_movement),
-                snake_movement.run_if(on_timer(Duration::from_millis(150))),
+                autopilot_steer.after(snake_direction).before(snake_movement),
+                snake_movement.run_if(movement_due),
                 snake_eating.after(snake_movement),
                 snake_growing.after(snake_eating),
                 game_over.after(snake_eating).after(snake_movement),
                 spawn_food.run_if(on_timer(Duration::from_millis(1500))),
-            ),
+            )
+                .run_if(in_state(GameState::Playing)),
         )
         .add_systems(PostUpdate, (size_scaling, position_translation))
         .add_plugins(DefaultPlugins.set(WindowPlugin {
@@ -55,6 +102,130 @@ fn main() {
         .run();
 }
 
+// GAME STATE
+
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+enum GameState {
+    #[default]
+    Menu,
+    Playing,
+    Paused,
+    GameOver,
+}
+
+fn despawn_screen<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Toggles between `Playing` and `Paused` on `Esc`, regardless of which of
+/// the two states we're currently in.
+fn pause_toggle(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Escape) {
+        return;
+    }
+    match state.get() {
+        GameState::Playing => next_state.set(GameState::Paused),
+        GameState::Paused => next_state.set(GameState::Playing),
+        _ => {}
+    }
+}
+
+// MENU SCREEN
+
+#[derive(Component)]
+struct MenuScreen;
+
+fn menu_setup(mut commands: Commands) {
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section(
+                "SNAKE\nPress Space to start",
+                TextStyle {
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ),
+            ..default()
+        })
+        .insert(MenuScreen);
+}
+
+fn menu_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        next_state.set(GameState::Playing);
+    }
+}
+
+// PAUSE SCREEN
+
+#[derive(Component)]
+struct PauseScreen;
+
+fn pause_setup(mut commands: Commands) {
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section(
+                "Paused",
+                TextStyle {
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ),
+            ..default()
+        })
+        .insert(PauseScreen);
+}
+
+// GAME OVER SCREEN
+
+#[derive(Component)]
+struct GameOverScreen;
+
+fn game_over_setup(mut commands: Commands, score: Res<Score>) {
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section(
+                format!(
+                    "Game Over\nScore: {}  Best: {}\nPress Space to restart",
+                    score.current, score.best
+                ),
+                TextStyle {
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ),
+            ..default()
+        })
+        .insert(GameOverScreen);
+}
+
+fn game_over_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    commands: Commands,
+    segments: ResMut<SnakeSegments>,
+    level: Res<Level>,
+    mut score: ResMut<Score>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        spawn_snake(commands, segments, level);
+        score.current = 0;
+        next_state.set(GameState::Playing);
+    }
+}
+
 // SETUP
 
 fn setup_camera(mut commands: Commands) {
@@ -70,7 +241,12 @@ struct SnakeHead {
     direction: Direction,
 }
 
-fn spawn_snake(mut commands: Commands, mut segments: ResMut<SnakeSegments>) {
+fn spawn_snake(mut commands: Commands, mut segments: ResMut<SnakeSegments>, level: Res<Level>) {
+    let head_position = level.snake_start.unwrap_or(Position { x: 3, y: 3 });
+    let tail_position = Position {
+        x: head_position.x,
+        y: head_position.y - 1,
+    };
     *segments = SnakeSegments(vec![
         commands
             .spawn(SpriteBundle {
@@ -84,9 +260,9 @@ fn spawn_snake(mut commands: Commands, mut segments: ResMut<SnakeSegments>) {
                 direction: Direction::Up,
             })
             .insert(Size::square(0.8))
-            .insert(Position { x: 3, y: 3 })
+            .insert(head_position)
             .id(),
-        spawn_snake_segment(commands, Position { x: 3, y: 2 }),
+        spawn_snake_segment(commands, tail_position),
     ])
 }
 // SNAKE SEGMENTS
@@ -134,29 +310,9 @@ impl Direction {
     }
 }
 
-fn snake_direction(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut head_positions: Query<&mut SnakeHead>,
-) {
-    for mut snake_head in head_positions.iter_mut() {
-        let next_dir = if keyboard_input.pressed(KeyCode::ArrowLeft) {
-            Direction::Left
-        } else if keyboard_input.pressed(KeyCode::ArrowRight) {
-            Direction::Right
-        } else if keyboard_input.pressed(KeyCode::ArrowUp) {
-            Direction::Up
-        } else if keyboard_input.pressed(KeyCode::ArrowDown) {
-            Direction::Down
-        } else {
-            snake_head.direction
-        };
-        if next_dir != snake_head.direction.opposite() {
-            snake_head.direction = next_dir;
-        }
-    }
-}
-
 fn snake_movement(
+    config: Res<ArenaConfig>,
+    level: Res<Level>,
     segments: Res<SnakeSegments>,
     mut game_over_writer: EventWriter<GameOverEvent>,
     mut heads: Query<(&SnakeHead, Entity)>,
@@ -179,13 +335,12 @@ fn snake_movement(
             Direction::Down => head_position.y -= 1,
         }
 
-        // If we're out of bound, game over
-        if head_position.x < 0
-            || head_position.y < 0
-            || head_position.y > ARENA_HEIGHT as i32
-            || head_position.x > ARENA_WIDTH as i32
-            || segment_positions.contains(&head_position)
-        {
+        match resolve_bounds(*head_position, &config) {
+            Some(resolved) => *head_position = resolved,
+            None => game_over_writer.send(GameOverEvent),
+        }
+
+        if segment_positions.contains(&head_position) || level.walls.contains(&head_position) {
             game_over_writer.send(GameOverEvent);
         }
 
@@ -208,9 +363,12 @@ fn snake_movement(
 #[derive(Event)]
 struct GrowthEvent;
 
+const POINTS_PER_FOOD: u32 = 10;
+
 fn snake_eating(
     mut commands: Commands,
     mut growth_writer: EventWriter<GrowthEvent>,
+    mut score: ResMut<Score>,
     foods: Query<(&Position, Entity), With<Food>>,
     heads: Query<&Position, With<SnakeHead>>,
 ) {
@@ -219,6 +377,7 @@ fn snake_eating(
             if food_pos == snake_head_pos {
                 commands.entity(food_ent).despawn();
                 growth_writer.send(GrowthEvent);
+                score.current += POINTS_PER_FOOD;
             }
         }
     }
@@ -249,17 +408,22 @@ const FOOD_COLOR: Color = Color::srgb(1.0, 0.0, 1.0);
 #[derive(Component)]
 struct Food;
 
-fn spawn_food(mut commands: Commands, snake_positions: Query<&Position, Without<Food>>) {
-    fn gen_pos() -> Position {
-        Position {
-            x: (random::<f32>() * ARENA_WIDTH as f32) as i32,
-            y: (random::<f32>() * ARENA_HEIGHT as f32) as i32,
-        }
-    }
+fn spawn_food(
+    mut commands: Commands,
+    config: Res<ArenaConfig>,
+    level: Res<Level>,
+    snake_positions: Query<&Position, Without<Food>>,
+) {
+    let gen_pos = || Position {
+        x: (random::<f32>() * config.width as f32) as i32,
+        y: (random::<f32>() * config.height as f32) as i32,
+    };
 
+    // `snake_positions` also picks up walls, since they're `Without<Food>`
+    // too, so the only extra exclusions to check are the level's.
     let mut next_pos = gen_pos();
     let positions_vec: Vec<&Position> = snake_positions.iter().collect();
-    while positions_vec.contains(&&next_pos) {
+    while positions_vec.contains(&&next_pos) || level.spawn_exclusions.contains(&next_pos) {
         next_pos = gen_pos();
     }
 
@@ -277,10 +441,7 @@ fn spawn_food(mut commands: Commands, snake_positions: Query<&Position, Without<
         })
         .insert(Food)
         .insert(Size::square(0.8))
-        .insert(Position {
-            x: (random::<f32>() * ARENA_WIDTH as f32) as i32,
-            y: (random::<f32>() * ARENA_HEIGHT as f32) as i32,
-        });
+        .insert(next_pos);
 }
 
 // GAME OVER
@@ -288,27 +449,155 @@ fn spawn_food(mut commands: Commands, snake_positions: Query<&Position, Without<
 #[derive(Event)]
 struct GameOverEvent;
 
+/// On game over we no longer respawn immediately: we tear down the board
+/// and hand off to `GameState::GameOver`, which shows the restart prompt
+/// until the player presses Space.
 fn game_over(
     mut commands: Commands,
     mut game_over_reader: EventReader<GameOverEvent>,
-    entities: Query<Entity, With<Position>>,
+    entities: Query<Entity, (With<Position>, Without<Wall>)>,
     mut segments_res: ResMut<SnakeSegments>,
+    mut score: ResMut<Score>,
+    mut next_state: ResMut<NextState<GameState>>,
 ) {
     if game_over_reader.read().next().is_some() {
         for ent in entities.iter() {
             commands.entity(ent).despawn();
         }
         segments_res.0 = vec![];
-        spawn_snake(commands, segments_res);
+        if score.current > score.best {
+            score.best = score.current;
+        }
+        save_high_score(&score);
+        next_state.set(GameState::GameOver);
+    }
+}
+
+// SCORE
+
+#[derive(Resource, Default)]
+struct Score {
+    current: u32,
+    best: u32,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct HighScoreFile {
+    best: u32,
+}
+
+fn high_score_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("snake")
+        .join("high_score.json")
+}
+
+fn load_high_score(mut score: ResMut<Score>) {
+    let path = high_score_path();
+    if let Ok(contents) = fs::read_to_string(&path) {
+        if let Ok(saved) = serde_json::from_str::<HighScoreFile>(&contents) {
+            score.best = saved.best;
+        }
+    }
+}
+
+fn save_high_score(score: &Score) {
+    let path = high_score_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(contents) = serde_json::to_string(&HighScoreFile { best: score.best }) {
+        let _ = fs::write(&path, contents);
+    }
+}
+
+#[derive(Component)]
+struct ScoreText;
+
+fn spawn_score_hud(mut commands: Commands) {
+    commands
+        .spawn(Text2dBundle {
+            text: Text::from_section(
+                "Score: 0  Best: 0",
+                TextStyle {
+                    font_size: 24.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ),
+            transform: Transform::from_xyz(0.0, 380.0, 10.0),
+            ..default()
+        })
+        .insert(ScoreText);
+}
+
+fn update_score_text(score: Res<Score>, mut text_query: Query<&mut Text, With<ScoreText>>) {
+    if !score.is_changed() {
+        return;
+    }
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!("Score: {}  Best: {}", score.current, score.best);
     }
 }
 
 // ARENA / SCALING
 
-const ARENA_WIDTH: u32 = 20;
-const ARENA_HEIGHT: u32 = 20;
+/// The size and rules of the playing field. `wrap` makes the snake reappear
+/// on the opposite edge instead of dying when it crosses a boundary.
+#[derive(Resource, Clone, Copy)]
+struct ArenaConfig {
+    width: u32,
+    height: u32,
+    tick_rate: Duration,
+    wrap: bool,
+}
 
-#[derive(Component, Clone, Copy, PartialEq, Eq)]
+impl Default for ArenaConfig {
+    fn default() -> Self {
+        Self {
+            width: 20,
+            height: 20,
+            tick_rate: Duration::from_millis(150),
+            wrap: false,
+        }
+    }
+}
+
+/// Given a head position that may have just left the arena on this tick,
+/// returns where it should end up: unchanged if it's still in bounds,
+/// wrapped to the opposite edge if `wrap` is enabled, or `None` if this
+/// should be game over.
+fn resolve_bounds(pos: Position, config: &ArenaConfig) -> Option<Position> {
+    let out_of_bounds = pos.x < 0
+        || pos.y < 0
+        || pos.y > config.height as i32
+        || pos.x > config.width as i32;
+
+    if !out_of_bounds {
+        return Some(pos);
+    }
+    if !config.wrap {
+        return None;
+    }
+    Some(Position {
+        x: pos.x.rem_euclid(config.width as i32 + 1),
+        y: pos.y.rem_euclid(config.height as i32 + 1),
+    })
+}
+
+/// Run condition mirroring `on_timer`, but reading its period from
+/// `ArenaConfig` instead of a value baked in at schedule-build time.
+fn movement_due(
+    time: Res<Time>,
+    config: Res<ArenaConfig>,
+    mut timer: Local<Option<Timer>>,
+) -> bool {
+    let timer = timer.get_or_insert_with(|| Timer::new(config.tick_rate, TimerMode::Repeating));
+    timer.tick(time.delta()).just_finished()
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug)]
 struct Position {
     x: i32,
     y: i32,
@@ -329,20 +618,22 @@ impl Size {
 }
 
 fn size_scaling(
+    config: Res<ArenaConfig>,
     windows: Query<&Window, With<PrimaryWindow>>,
     mut entites_to_scale: Query<(&Size, &mut Transform)>,
 ) {
     let window = windows.single();
     for (size, mut transform) in entites_to_scale.iter_mut() {
         transform.scale = Vec3::new(
-            size.width / ARENA_WIDTH as f32 * window.width() as f32,
-            size.height / ARENA_WIDTH as f32 * window.height() as f32,
+            size.width / config.width as f32 * window.width() as f32,
+            size.height / config.height as f32 * window.height() as f32,
             1.0,
         );
     }
 }
 
 fn position_translation(
+    config: Res<ArenaConfig>,
     windows: Query<&Window, With<PrimaryWindow>>,
     mut entites_to_scale: Query<(&Position, &mut Transform)>,
 ) {
@@ -355,9 +646,94 @@ fn position_translation(
 
     for (pos, mut transform) in entites_to_scale.iter_mut() {
         transform.translation = Vec3::new(
-            convert(pos.x as f32, window.width(), ARENA_WIDTH as f32),
-            convert(pos.y as f32, window.height(), ARENA_HEIGHT as f32),
+            convert(pos.x as f32, window.width(), config.width as f32),
+            convert(pos.y as f32, window.height(), config.height as f32),
             1.0,
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wrapping_config() -> ArenaConfig {
+        ArenaConfig {
+            width: 20,
+            height: 20,
+            wrap: true,
+            ..ArenaConfig::default()
+        }
+    }
+
+    #[test]
+    fn in_bounds_position_is_unchanged() {
+        let config = wrapping_config();
+        let pos = Position { x: 5, y: 5 };
+        assert_eq!(resolve_bounds(pos, &config), Some(pos));
+    }
+
+    #[test]
+    fn without_wrap_leaving_the_arena_is_game_over() {
+        let config = ArenaConfig {
+            wrap: false,
+            ..wrapping_config()
+        };
+        assert_eq!(resolve_bounds(Position { x: -1, y: 5 }, &config), None);
+        assert_eq!(
+            resolve_bounds(
+                Position {
+                    x: config.width as i32 + 1,
+                    y: 5
+                },
+                &config
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn wrap_reappears_on_the_opposite_edge() {
+        let config = wrapping_config();
+
+        // Walking off the left edge reappears at the (inclusive) right edge.
+        assert_eq!(
+            resolve_bounds(Position { x: -1, y: 5 }, &config),
+            Some(Position {
+                x: config.width as i32,
+                y: 5
+            })
+        );
+
+        // Walking off the (inclusive) right edge reappears at the left edge.
+        assert_eq!(
+            resolve_bounds(
+                Position {
+                    x: config.width as i32 + 1,
+                    y: 5
+                },
+                &config
+            ),
+            Some(Position { x: 0, y: 5 })
+        );
+
+        // Same behavior on the vertical axis.
+        assert_eq!(
+            resolve_bounds(Position { x: 5, y: -1 }, &config),
+            Some(Position {
+                x: 5,
+                y: config.height as i32
+            })
+        );
+        assert_eq!(
+            resolve_bounds(
+                Position {
+                    x: 5,
+                    y: config.height as i32 + 1
+                },
+                &config
+            ),
+            Some(Position { x: 5, y: 0 })
+        );
+    }
+}